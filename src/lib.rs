@@ -1,4 +1,27 @@
-use std::{sync::atomic::{AtomicI32, Ordering, AtomicU32, AtomicUsize}, cell::UnsafeCell};
+use std::{cell::UnsafeCell, ops::{Deref, DerefMut}};
+use parking_lot_core::{self, FilterOp, ParkToken, SpinWait, UnparkToken};
+
+// Every atomic used by the Lock/Storage state machine routes through here, so that under
+// `--cfg loom` (see the `loom_tests` module at the bottom of this file) it runs against
+// loom's shadow atomics instead of std's, letting loom explore their interleavings.
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicUsize, Ordering};
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicUsize, Ordering};
+
+// Same idea as the atomics above, but for the one spot that needs real mutual exclusion
+// rather than a single atomic op: loom's Mutex is what its model checker knows how to
+// schedule around without exploding the state space the way a hand-rolled spin loop would.
+#[cfg(not(loom))]
+use std::sync::Mutex;
+#[cfg(loom)]
+use loom::sync::Mutex;
+
+// ParkToken carried by a thread parked waiting to read (blocked behind a writer).
+const READER_PARK_TOKEN: ParkToken = ParkToken(0);
+// ParkToken carried by a thread parked waiting for exclusive access (create/update/put).
+const WRITER_PARK_TOKEN: ParkToken = ParkToken(1);
+const DEFAULT_UNPARK_TOKEN: UnparkToken = UnparkToken(0);
 
 #[repr(C)]
 pub struct Lock {
@@ -7,12 +30,17 @@ pub struct Lock {
     // refcount == 1 means protected data exists and not in use, 'update' and 'read' operations are allowed
     // refcount > 1 means protected data exists and is being read, only 'read' operation is allowed
     refcount: AtomicI32,
+    // true while an upgradeable reader is outstanding (held via read_upgradeable). Independent
+    // of refcount: the upgradeable reader also holds a normal read slot in refcount, this flag
+    // only serializes against a second upgradeable reader.
+    upgradeable: AtomicBool,
 }
 
 impl Default for Lock {
     fn default() -> Self {
         Self {
             refcount: AtomicI32::new(0),
+            upgradeable: AtomicBool::new(false),
         }
     }
 }
@@ -35,6 +63,7 @@ impl Lock {
             let r = f();
             self.refcount
                 .store(if r.is_some() { 1 } else { 0 }, Ordering::Release);
+            self.wake_after_write_release();
             r
         } else {
             None
@@ -57,6 +86,7 @@ impl Lock {
             let r = f();
             self.refcount
                 .store(if r.is_some() { 1 } else { 0 }, Ordering::Release);
+            self.wake_after_write_release();
             r
         } else {
             None
@@ -80,9 +110,259 @@ impl Lock {
             return None;
         }
         let r = f();
-        self.refcount.fetch_sub(1, Ordering::Release);
+        if self.refcount.fetch_sub(1, Ordering::Release) == 2 {
+            // this was the only active reader; the slot is back to refcount 1, which a
+            // parked updater may now be able to use
+            self.wake_after_read_release();
+        }
         Some(r)
     }
+
+    // Blocking counterpart to `read`: spins briefly under contention, then parks the calling
+    // thread until a writer releases the lock, instead of returning `None`.
+    pub fn read_blocking<R>(&self, f: impl FnOnce() -> R) -> R {
+        let key = self.park_key();
+        let mut spinwait = SpinWait::new();
+        loop {
+            if self
+                .refcount
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |x| {
+                    if x > 0 {
+                        Some(x + 1)
+                    } else {
+                        None
+                    }
+                })
+                .is_ok()
+            {
+                break;
+            }
+            if spinwait.spin() {
+                continue;
+            }
+            unsafe {
+                parking_lot_core::park(
+                    key,
+                    || self.refcount.load(Ordering::Relaxed) <= 0,
+                    || {},
+                    |_, _| {},
+                    READER_PARK_TOKEN,
+                    None,
+                );
+            }
+            spinwait.reset();
+        }
+        let r = f();
+        if self.refcount.fetch_sub(1, Ordering::Release) == 2 {
+            self.wake_after_read_release();
+        }
+        r
+    }
+
+    // Blocking counterpart to `update`: spins briefly under contention, then parks the calling
+    // thread until the slot becomes updatable (refcount == 1), instead of returning `None`.
+    pub fn update_blocking<R>(&self, f: impl FnOnce() -> Option<R>) -> Option<R> {
+        let key = self.park_key();
+        let mut spinwait = SpinWait::new();
+        loop {
+            if self
+                .refcount
+                .compare_exchange_weak(1, -1, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                break;
+            }
+            if spinwait.spin() {
+                continue;
+            }
+            unsafe {
+                parking_lot_core::park(
+                    key,
+                    || self.refcount.load(Ordering::Relaxed) != 1,
+                    || {},
+                    |_, _| {},
+                    WRITER_PARK_TOKEN,
+                    None,
+                );
+            }
+            spinwait.reset();
+        }
+        let r = f();
+        self.refcount
+            .store(if r.is_some() { 1 } else { 0 }, Ordering::Release);
+        self.wake_after_write_release();
+        r
+    }
+
+    fn park_key(&self) -> usize {
+        &self.refcount as *const AtomicI32 as usize
+    }
+
+    // Wakes waiters after a release that leaves the slot readable: every parked reader, since
+    // reads can proceed concurrently, and (if none were waiting) one parked writer, since
+    // `create`/`update` both need the resulting refcount exclusively to themselves.
+    fn wake_after_write_release(&self) {
+        unsafe {
+            let readers = parking_lot_core::unpark_filter(
+                self.park_key(),
+                |token| {
+                    if token == READER_PARK_TOKEN {
+                        FilterOp::Unpark
+                    } else {
+                        FilterOp::Skip
+                    }
+                },
+                |_| DEFAULT_UNPARK_TOKEN,
+            );
+            if readers.unparked_threads == 0 {
+                parking_lot_core::unpark_one(self.park_key(), |_| DEFAULT_UNPARK_TOKEN);
+            }
+        }
+    }
+
+    // Wakes a single parked writer after the last reader leaves (refcount back to 1).
+    fn wake_after_read_release(&self) {
+        unsafe {
+            parking_lot_core::unpark_one(self.park_key(), |_| DEFAULT_UNPARK_TOKEN);
+        }
+    }
+
+    // Same acquisition as `read`, but returns a `ReadGuard` instead of taking a callback,
+    // so the caller can hold the read lock across a scope and release it by dropping the guard.
+    pub fn try_read(&self) -> Option<ReadGuard<'_>> {
+        self.refcount
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |x| {
+                if x > 0 {
+                    Some(x + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()?;
+        Some(ReadGuard { lock: self })
+    }
+
+    // Acquires a write lock whether the data is currently empty (refcount == 0, the `create`
+    // case) or present and unused (refcount == 1, the `update` case), and returns a `WriteGuard`
+    // instead of taking a callback. The guard releases the lock with refcount 1 on drop, unless
+    // `WriteGuard::clear` was called, in which case it releases with refcount 0.
+    pub fn try_write(&self) -> Option<WriteGuard<'_>> {
+        self.refcount
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |x| {
+                if x == 0 || x == 1 {
+                    Some(-1)
+                } else {
+                    None
+                }
+            })
+            .ok()?;
+        Some(WriteGuard { lock: self, keep: true })
+    }
+
+    // Acquires a read lock that can later be upgraded to exclusive access without ever
+    // releasing in between, avoiding the TOCTOU gap of `read` followed by `update`. At most
+    // one upgradeable reader is allowed at a time, though ordinary readers may still come and
+    // go alongside it; a writer is blocked the whole time, same as any other read.
+    pub fn read_upgradeable(&self) -> Option<UpgradableReadGuard<'_>> {
+        if self
+            .upgradeable
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return None;
+        }
+        if self
+            .refcount
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |x| {
+                if x > 0 {
+                    Some(x + 1)
+                } else {
+                    None
+                }
+            })
+            .is_err()
+        {
+            self.upgradeable.store(false, Ordering::Release);
+            return None;
+        }
+        Some(UpgradableReadGuard { lock: self })
+    }
+}
+
+// RAII guard returned by `Lock::try_read`. Decrements the reference count on drop, releasing
+// the read lock acquired on construction.
+pub struct ReadGuard<'a> {
+    lock: &'a Lock,
+}
+
+impl<'a> Drop for ReadGuard<'a> {
+    fn drop(&mut self) {
+        if self.lock.refcount.fetch_sub(1, Ordering::Release) == 2 {
+            self.lock.wake_after_read_release();
+        }
+    }
+}
+
+// RAII guard returned by `Lock::try_write`. Restores the reference count to 1 (data present)
+// on drop, unless `clear` was called, in which case it restores 0 (data empty).
+pub struct WriteGuard<'a> {
+    lock: &'a Lock,
+    keep: bool,
+}
+
+impl<'a> WriteGuard<'a> {
+    // Mark the protected data as empty, so the lock is released with refcount 0 on drop
+    // instead of 1.
+    pub fn clear(&mut self) {
+        self.keep = false;
+    }
+}
+
+impl<'a> Drop for WriteGuard<'a> {
+    fn drop(&mut self) {
+        self.lock
+            .refcount
+            .store(if self.keep { 1 } else { 0 }, Ordering::Release);
+        self.lock.wake_after_write_release();
+    }
+}
+
+// RAII guard returned by `Lock::read_upgradeable`. Behaves like a `ReadGuard` unless
+// `try_upgrade` succeeds, in which case it is consumed into a `WriteGuard` instead of
+// releasing the read lock.
+pub struct UpgradableReadGuard<'a> {
+    lock: &'a Lock,
+}
+
+impl<'a> UpgradableReadGuard<'a> {
+    // Atomically transitions to exclusive access if this is the only outstanding reader
+    // (refcount == 2, i.e. no plain reader is concurrently active), consuming self into a
+    // `WriteGuard`. Otherwise gives the read lock back unchanged so the caller can keep
+    // reading or try again later.
+    pub fn try_upgrade(self) -> Result<WriteGuard<'a>, Self> {
+        if self
+            .lock
+            .refcount
+            .compare_exchange(2, -1, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            let lock = self.lock;
+            lock.upgradeable.store(false, Ordering::Release);
+            std::mem::forget(self);
+            Ok(WriteGuard { lock, keep: true })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<'a> Drop for UpgradableReadGuard<'a> {
+    fn drop(&mut self) {
+        if self.lock.refcount.fetch_sub(1, Ordering::Release) == 2 {
+            self.lock.wake_after_read_release();
+        }
+        self.lock.upgradeable.store(false, Ordering::Release);
+    }
 }
 
 
@@ -90,6 +370,14 @@ impl Lock {
 pub struct StorageHdr {
     size: usize,
     next_id: AtomicUsize,
+    // number of live Subscribers; put() arms each slot's pending-reader count with this
+    // many reads before it can be reclaimed
+    subscribers: AtomicUsize,
+    // Guards `next_id` and `subscribers` against each other: a put's (id, subscriber count to
+    // arm) snapshot and a subscriber's (cursor, registration) snapshot must never interleave,
+    // or a subscriber whose cursor skips an id can still end up armed for it (or vice versa),
+    // stranding a pending-read credit forever.
+    registration: Mutex<()>,
 }
 
 impl StorageHdr {
@@ -97,16 +385,58 @@ impl StorageHdr {
         Self {
             size,
             next_id: AtomicUsize::new(0),
+            subscribers: AtomicUsize::new(0),
+            registration: Mutex::new(()),
         }
     }
+
+    // Shared park key for `put_blocking`: a blocked putter doesn't know in advance which slot
+    // will free up, so it parks here instead of on any one slot's `Lock::park_key`, and every
+    // release that could make a slot reclaimable wakes this key too.
+    fn put_park_key(&self) -> usize {
+        self as *const StorageHdr as usize
+    }
+
+    fn wake_blocked_putters(&self) {
+        unsafe {
+            parking_lot_core::unpark_all(self.put_park_key(), DEFAULT_UNPARK_TOKEN);
+        }
+    }
+
+    // Reserves the next put's id together with a snapshot of the subscriber count it must arm,
+    // as a single atomic step: taking the id and reading `subscribers` in one critical section
+    // (rather than one after the other, unguarded) is what stops a `subscribe()` from landing
+    // in between and seeing a torn mix of the two.
+    fn reserve_put(&self) -> (usize, usize) {
+        let _guard = self.registration.lock().unwrap();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let subscribers = self.subscribers.load(Ordering::Relaxed);
+        (id, subscribers)
+    }
+
+    // Registers a new subscriber and snapshots its starting cursor as a single atomic step,
+    // mirroring `reserve_put` from the other side: the cursor must land exactly on the first id
+    // this subscriber is guaranteed to be armed for, never skipping one it was counted in.
+    fn register_subscriber(&self) -> usize {
+        let _guard = self.registration.lock().unwrap();
+        self.subscribers.fetch_add(1, Ordering::Relaxed);
+        self.next_id.load(Ordering::Relaxed)
+    }
 }
 
 
 #[repr(C)]
 pub struct ItemHdr {
     lock: Lock,
+    // pending subscriber reads: put() arms this to the live subscriber count, each
+    // Subscriber::next that consumes the slot decrements it, and put() only reclaims the
+    // slot via `update` once it reaches zero, so a producer lapping a slow consumer is
+    // refused instead of clobbering unread data.
     refcount: AtomicU32,
-    id: usize,
+    // generation stamp: put() writes the chosen id here while holding the write lock,
+    // get() compares it against the Token's id to detect a slot reclaimed by wraparound.
+    // invariant: id <= token.id always, equality meaning the token is still live.
+    id: UnsafeCell<usize>,
 }
 
 impl Default for ItemHdr {
@@ -114,7 +444,7 @@ impl Default for ItemHdr {
         Self {
             lock: Lock::default(),
             refcount: AtomicU32::new(0),
-            id: 0,
+            id: UnsafeCell::new(0),
         }
     }
 }
@@ -145,32 +475,345 @@ impl<'a, T> Storage<'a, T> {
         self.header.size
     }
 
+    // Stamps a slot with this put's id and arms it with the subscriber count captured
+    // alongside that id at reservation time (see `StorageHdr::reserve_put`), shared by `put`
+    // and `put_blocking`'s identical claim step. Stamping the id before releasing the write
+    // lock means a stale Token from before wraparound is rejected by get().
+    //
+    // Exclusivity here comes from the Lock's refcount state machine (only create/update ever
+    // reach this), not from the borrow checker, same as every other `&mut` handed out of an
+    // UnsafeCell slot in this file.
+    #[allow(clippy::mut_from_ref)]
+    fn claim(&self, hdr: &'a ItemHdr, pos: usize, id: usize, subscribers: usize) -> Option<&'a mut T> {
+        unsafe { *hdr.id.get() = id; }
+        hdr.refcount.store(subscribers as u32, Ordering::Release);
+        Some(unsafe { &mut *self.items[pos].get() })
+    }
+
     pub fn put(&self, f: impl FnOnce(&mut T) -> bool ) -> Option<Token> {
-        let id_start = self.header.next_id.fetch_add(1, Ordering::Relaxed);
+        let (id_start, subscribers) = self.header.reserve_put();
         // try all items in the ring buffer starting from pos, wrapping around the end and finishing at pos-1
         for i in 0..self.header.size {
             let id = id_start + i;
             let pos = (id_start + i) % self.header.size;
             let hdr = &self.item_hdrs[pos];
-            if let Some(item) = hdr.lock.create(|| {
-                Some(unsafe { &mut *self.items[pos].get() })
-            }) {
+            let claim = || self.claim(hdr, pos, id, subscribers);
+            if let Some(item) = hdr.lock.create(claim) {
                 // Token is not given to anyone yet so it's safe to access the data outside of the write lock
                 f(item);
                 return Some(Token { id});
             }
+            // an occupied slot can be reclaimed once every subscriber has consumed it
+            if hdr.refcount.load(Ordering::Acquire) == 0 {
+                if let Some(item) = hdr.lock.update(claim) {
+                    f(item);
+                    return Some(Token { id});
+                }
+            }
         }
         // no free slots
         None
     }
 
+    // Blocking counterpart to `put`: instead of giving up after one sweep of the ring, spins
+    // briefly then parks on a ring-wide wake point and retries the whole sweep once any slot
+    // changes state, until some slot frees up.
+    pub fn put_blocking(&self, f: impl FnOnce(&mut T) -> bool) -> Token {
+        let mut spinwait = SpinWait::new();
+        loop {
+            let (id_start, subscribers) = self.header.reserve_put();
+            for i in 0..self.header.size {
+                let id = id_start + i;
+                let pos = (id_start + i) % self.header.size;
+                let hdr = &self.item_hdrs[pos];
+                let claim = || self.claim(hdr, pos, id, subscribers);
+                if let Some(item) = hdr.lock.create(claim) {
+                    f(item);
+                    return Token { id };
+                }
+                if hdr.refcount.load(Ordering::Acquire) == 0 {
+                    if let Some(item) = hdr.lock.update(claim) {
+                        f(item);
+                        return Token { id };
+                    }
+                }
+            }
+            // every slot was occupied on this sweep; park on the shared wake point rather than
+            // any single slot, since it's some *other* slot that's likely to free up next, and
+            // re-check every slot (not just the one we happened to park on) once woken.
+            if spinwait.spin() {
+                continue;
+            }
+            unsafe {
+                parking_lot_core::park(
+                    self.header.put_park_key(),
+                    || {
+                        self.item_hdrs.iter().all(|hdr| {
+                            let lock_refcount = hdr.lock.refcount.load(Ordering::Relaxed);
+                            lock_refcount != 0
+                                && !(lock_refcount == 1
+                                    && hdr.refcount.load(Ordering::Relaxed) == 0)
+                        })
+                    },
+                    || {},
+                    |_, _| {},
+                    WRITER_PARK_TOKEN,
+                    None,
+                );
+            }
+            spinwait.reset();
+        }
+    }
+
     pub fn get<R>(&self, token: Token, f: impl FnOnce(&T)-> R) -> Option<R> {
         let pos = token.id % self.header.size;
         let hdr = &self.item_hdrs[pos];
-        hdr.lock.read(|| {
-            f(unsafe { &*self.items[pos].get() })
+        let result = hdr.lock.read(|| {
+            if unsafe { *hdr.id.get() } != token.id {
+                // slot was reclaimed by a later put since this token was issued
+                None
+            } else {
+                Some(f(unsafe { &*self.items[pos].get() }))
+            }
+        });
+        if result.is_some() {
+            // the read just released may have been the last one holding this slot, so a
+            // parked put_blocking could now find it updatable
+            self.header.wake_blocked_putters();
+        }
+        result.flatten()
+    }
+
+    // RAII counterpart to `get`: holds the read lock until the returned guard is dropped,
+    // so the caller can keep the slot locked across a scope instead of going through a callback.
+    pub fn read(&self, token: Token) -> Option<StorageReadGuard<'a, T>> {
+        let pos = token.id % self.header.size;
+        let hdr = &self.item_hdrs[pos];
+        let guard = hdr.lock.try_read()?;
+        if unsafe { *hdr.id.get() } != token.id {
+            // slot was reclaimed by a later put since this token was issued
+            return None;
+        }
+        Some(StorageReadGuard {
+            guard: Some(guard),
+            header: self.header,
+            item: unsafe { &*self.items[pos].get() },
+        })
+    }
+
+    // RAII counterpart to updating a slot in place: holds the write lock until the returned
+    // guard is dropped, so the caller can keep the slot locked across a scope. The guarded data
+    // stays present on drop; call `StorageWriteGuard::clear` first to release the slot empty.
+    pub fn write(&self, token: Token) -> Option<StorageWriteGuard<'a, T>> {
+        let pos = token.id % self.header.size;
+        let hdr = &self.item_hdrs[pos];
+        let guard = hdr.lock.try_write()?;
+        if unsafe { *hdr.id.get() } != token.id {
+            // slot was reclaimed by a later put since this token was issued
+            return None;
+        }
+        Some(StorageWriteGuard {
+            guard: Some(guard),
+            header: self.header,
+            item: unsafe { &mut *self.items[pos].get() },
         })
     }
+
+    // Registers a new broadcast subscriber, starting it at the current write position so it
+    // only observes items put after this call. Every live Subscriber must consume an item
+    // before put() will reclaim the slot it lives in.
+    pub fn subscribe(&self) -> Subscriber<'a, T> {
+        let cursor = self.header.register_subscriber();
+        Subscriber {
+            storage: Storage::new(self.header, self.items, self.item_hdrs),
+            cursor: AtomicUsize::new(cursor),
+        }
+    }
+
+    // Like `read`, but the returned guard can be atomically upgraded to a `StorageWriteGuard`
+    // via `StorageUpgradableGuard::try_upgrade`, avoiding the TOCTOU gap of reading then
+    // separately calling `write`.
+    pub fn get_for_update(&self, token: Token) -> Option<StorageUpgradableGuard<'a, T>> {
+        let pos = token.id % self.header.size;
+        let hdr = &self.item_hdrs[pos];
+        let guard = hdr.lock.read_upgradeable()?;
+        if unsafe { *hdr.id.get() } != token.id {
+            // slot was reclaimed by a later put since this token was issued
+            return None;
+        }
+        Some(StorageUpgradableGuard {
+            guard,
+            header: self.header,
+            item: &self.items[pos],
+        })
+    }
+}
+
+// RAII read handle returned by `Storage::read`, dereferencing to the guarded item. The inner
+// guard is wrapped in `Option` so `Drop` can release it before waking any parked `put_blocking`
+// caller, instead of the automatic field-drop ordering (wake first, release after) that would
+// let the wake see a slot that isn't actually free yet.
+pub struct StorageReadGuard<'a, T> {
+    guard: Option<ReadGuard<'a>>,
+    header: &'a StorageHdr,
+    item: &'a T,
+}
+
+impl<'a, T> Deref for StorageReadGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.item
+    }
+}
+
+impl<'a, T> Drop for StorageReadGuard<'a, T> {
+    fn drop(&mut self) {
+        self.guard.take();
+        self.header.wake_blocked_putters();
+    }
+}
+
+// RAII write handle returned by `Storage::write`, dereferencing to the guarded item. See
+// `StorageReadGuard` for why the inner guard is wrapped in `Option`.
+pub struct StorageWriteGuard<'a, T> {
+    guard: Option<WriteGuard<'a>>,
+    header: &'a StorageHdr,
+    item: &'a mut T,
+}
+
+impl<'a, T> StorageWriteGuard<'a, T> {
+    // Release the slot as empty instead of present once this guard is dropped.
+    pub fn clear(&mut self) {
+        self.guard.as_mut().expect("guard taken before drop").clear();
+    }
+}
+
+impl<'a, T> Deref for StorageWriteGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.item
+    }
+}
+
+impl<'a, T> DerefMut for StorageWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.item
+    }
+}
+
+impl<'a, T> Drop for StorageWriteGuard<'a, T> {
+    fn drop(&mut self) {
+        self.guard.take();
+        self.header.wake_blocked_putters();
+    }
+}
+
+// RAII handle returned by `Storage::get_for_update`: reads like a `StorageReadGuard` but can
+// be atomically upgraded to a `StorageWriteGuard` via `try_upgrade`.
+pub struct StorageUpgradableGuard<'a, T> {
+    guard: UpgradableReadGuard<'a>,
+    header: &'a StorageHdr,
+    item: &'a UnsafeCell<T>,
+}
+
+impl<'a, T> StorageUpgradableGuard<'a, T> {
+    // Atomically transitions to exclusive access if this is the only outstanding reader,
+    // consuming self into a `StorageWriteGuard`. Otherwise gives the guard back unchanged.
+    pub fn try_upgrade(self) -> Result<StorageWriteGuard<'a, T>, Self> {
+        let StorageUpgradableGuard { guard, header, item } = self;
+        match guard.try_upgrade() {
+            Ok(write_guard) => Ok(StorageWriteGuard {
+                guard: Some(write_guard),
+                header,
+                item: unsafe { &mut *item.get() },
+            }),
+            Err(guard) => Err(StorageUpgradableGuard { guard, header, item }),
+        }
+    }
+}
+
+impl<'a, T> Deref for StorageUpgradableGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.item.get() }
+    }
+}
+
+// A broadcast (MPMC) consumer: every Subscriber on a Storage observes each item put into it
+// exactly once, via its own monotonically advancing cursor, independent of other subscribers.
+#[repr(C)]
+pub struct Subscriber<'a, T: 'a> {
+    storage: Storage<'a, T>,
+    cursor: AtomicUsize,
+}
+
+impl<'a, T> Subscriber<'a, T> {
+    // Reads and clones the next unconsumed item, if one has been put yet, advancing the
+    // cursor past it and releasing this subscriber's pending read on the slot. Returns None
+    // if nothing newer than the cursor has been put at this position yet. `next()` takes
+    // `&self`, so concurrent calls on the same Subscriber are possible: the cursor advance is
+    // done with a compare_exchange against the cursor value the slot was read under, so only
+    // one of two racing calls ever claims a given slot and pays off its pending-read credit;
+    // the loser retries from whatever cursor the winner left behind.
+    pub fn next(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        loop {
+            let cursor = self.cursor.load(Ordering::Relaxed);
+            let pos = cursor % self.storage.header.size;
+            let hdr = &self.storage.item_hdrs[pos];
+            let claimed = hdr
+                .lock
+                .read(|| {
+                    let id = unsafe { *hdr.id.get() };
+                    if id < cursor {
+                        // nothing new put here since this subscriber last consumed it
+                        return None;
+                    }
+                    if self
+                        .cursor
+                        .compare_exchange(cursor, id + 1, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_err()
+                    {
+                        // a concurrent next() already claimed this slot first
+                        return None;
+                    }
+                    hdr.refcount.fetch_sub(1, Ordering::AcqRel);
+                    Some(unsafe { (*self.storage.items[pos].get()).clone() })
+                })
+                .flatten();
+            match claimed {
+                Some(value) => {
+                    self.storage.header.wake_blocked_putters();
+                    return Some(value);
+                }
+                // the cursor moved underneath us (a concurrent call won the race); retry from
+                // its new position instead of spuriously returning None
+                None if self.cursor.load(Ordering::Relaxed) != cursor => continue,
+                None => return None,
+            }
+        }
+    }
+}
+
+impl<'a, T> Drop for Subscriber<'a, T> {
+    fn drop(&mut self) {
+        self.storage.header.subscribers.fetch_sub(1, Ordering::Relaxed);
+        // Release this subscriber's pending-read credit on every slot it hasn't consumed yet.
+        // A slot's current id is >= cursor exactly when it was armed with this subscriber's
+        // credit and `next()` never claimed it; leaving that credit unpaid would let put()
+        // refuse to reclaim the slot forever once this subscriber is gone.
+        let cursor = self.cursor.load(Ordering::Relaxed);
+        for hdr in self.storage.item_hdrs {
+            hdr.lock.read(|| {
+                if unsafe { *hdr.id.get() } >= cursor {
+                    hdr.refcount.fetch_sub(1, Ordering::AcqRel);
+                }
+            });
+        }
+        self.storage.header.wake_blocked_putters();
+    }
 }
 
 #[test]
@@ -182,6 +825,222 @@ fn test_init_storage() {
     assert_eq!(storage.size(), 10);
 }
 
+#[test]
+fn test_broadcast_subscribers_consume_once() {
+    let header = StorageHdr::new(1);
+    let items = [(); 1].map(|_| UnsafeCell::new(0));
+    let item_hdrs = [(); 1].map(|_| ItemHdr::default());
+    let storage = Storage::new(&header, &items, &item_hdrs);
+
+    let sub1 = storage.subscribe();
+    let sub2 = storage.subscribe();
+
+    storage.put(|x| { *x = 1; true }).unwrap();
+    assert_eq!(sub1.next(), Some(1));
+    // sub2 hasn't consumed it yet, so the only slot can't be reclaimed
+    assert!(storage.put(|x| { *x = 2; true }).is_none());
+
+    assert_eq!(sub2.next(), Some(1));
+    // both subscribers have caught up, so the slot can now be reused
+    storage.put(|x| { *x = 2; true }).unwrap();
+
+    assert_eq!(sub1.next(), Some(2));
+    assert_eq!(sub2.next(), Some(2));
+    assert_eq!(sub1.next(), None);
+}
+
+#[test]
+fn test_subscriber_next_concurrent_calls_consume_exactly_once() {
+    struct RingStorage {
+        header: StorageHdr,
+        items: [UnsafeCell<i32>; 1],
+        item_hdrs: [ItemHdr; 1],
+    }
+    unsafe impl Sync for RingStorage {}
+    fn view(ring: &RingStorage) -> Storage<'_, i32> {
+        Storage::new(&ring.header, &ring.items, &ring.item_hdrs)
+    }
+
+    let ring = RingStorage {
+        header: StorageHdr::new(1),
+        items: [UnsafeCell::new(0)],
+        item_hdrs: [ItemHdr::default()],
+    };
+    let sub = view(&ring).subscribe();
+    view(&ring).put(|x| { *x = 1; true }).unwrap();
+
+    struct SyncSubscriber<'a>(Subscriber<'a, i32>);
+    unsafe impl Sync for SyncSubscriber<'_> {}
+    fn next_of(sub: &SyncSubscriber<'_>) -> Option<i32> {
+        sub.0.next()
+    }
+
+    let sub = SyncSubscriber(sub);
+    let barrier = std::sync::Barrier::new(2);
+    let (a, b) = std::thread::scope(|s| {
+        let t1 = s.spawn(|| {
+            barrier.wait();
+            next_of(&sub)
+        });
+        let t2 = s.spawn(|| {
+            barrier.wait();
+            next_of(&sub)
+        });
+        (t1.join().unwrap(), t2.join().unwrap())
+    });
+
+    // exactly one of the two racing calls may claim the item and its pending-read credit
+    assert_eq!([a, b].into_iter().filter(|r| r.is_some()).count(), 1);
+    assert_eq!([a, b].into_iter().flatten().next(), Some(1));
+    // the credit was paid off exactly once, so the slot can be reused
+    assert!(view(&ring).put(|x| { *x = 2; true }).is_some());
+}
+
+#[test]
+fn test_dropped_subscriber_releases_pending_credit() {
+    let header = StorageHdr::new(1);
+    let items = [(); 1].map(|_| UnsafeCell::new(0));
+    let item_hdrs = [(); 1].map(|_| ItemHdr::default());
+    let storage = Storage::new(&header, &items, &item_hdrs);
+
+    let sub = storage.subscribe();
+    storage.put(|x| { *x = 1; true }).unwrap();
+    // sub never called next(), but dropping it must still pay off its pending-read credit,
+    // or the only slot would be refused forever
+    drop(sub);
+    assert!(storage.put(|x| { *x = 2; true }).is_some());
+}
+
+#[test]
+fn test_get_for_update_upgrade() {
+    let header = StorageHdr::new(1);
+    let items = [(); 1].map(|_| UnsafeCell::new(0));
+    let item_hdrs = [(); 1].map(|_| ItemHdr::default());
+    let storage = Storage::new(&header, &items, &item_hdrs);
+    let token = storage.put(|x| { *x = 1; true }).unwrap();
+
+    // a second upgradeable reader is refused while the first is outstanding
+    let guard = storage.get_for_update(Token { id: token.id }).unwrap();
+    assert!(storage.get_for_update(Token { id: token.id }).is_none());
+    assert_eq!(*guard, 1);
+
+    // sole outstanding reader: the upgrade succeeds without ever releasing the lock
+    let mut write_guard = guard.try_upgrade().ok().unwrap();
+    *write_guard = 2;
+    drop(write_guard);
+
+    assert_eq!(*storage.read(Token { id: token.id }).unwrap(), 2);
+}
+
+#[test]
+fn test_get_for_update_upgrade_fails_with_other_reader() {
+    let header = StorageHdr::new(1);
+    let items = [(); 1].map(|_| UnsafeCell::new(0));
+    let item_hdrs = [(); 1].map(|_| ItemHdr::default());
+    let storage = Storage::new(&header, &items, &item_hdrs);
+    let token = storage.put(|x| { *x = 1; true }).unwrap();
+
+    let guard = storage.get_for_update(Token { id: token.id }).unwrap();
+    let reader = storage.read(Token { id: token.id }).unwrap();
+    let guard = guard.try_upgrade().err().unwrap();
+    drop(reader);
+    assert!(guard.try_upgrade().is_ok());
+}
+
+#[test]
+fn test_stale_token_after_slot_reuse() {
+    let header = StorageHdr::new(1);
+    let items = [(); 1].map(|_| UnsafeCell::new(0));
+    let item_hdrs = [(); 1].map(|_| ItemHdr::default());
+    let storage = Storage::new(&header, &items, &item_hdrs);
+
+    let stale = storage.put(|x| { *x = 1; true }).unwrap();
+    assert_eq!(storage.get(Token { id: stale.id }, |x| *x), Some(1));
+
+    // simulate the only slot being reclaimed and overwritten by a later put with a newer id
+    item_hdrs[0].lock.update(|| {
+        unsafe { *item_hdrs[0].id.get() = stale.id + 1; }
+        unsafe { *items[0].get() = 2; }
+        Some(())
+    }).unwrap();
+
+    assert_eq!(storage.get(Token { id: stale.id }, |x| *x), None);
+    assert_eq!(storage.get(Token { id: stale.id + 1 }, |x| *x), Some(2));
+}
+
+#[test]
+fn test_storage_guard_api() {
+    let header = StorageHdr::new(1);
+    let items = [(); 1].map(|_| UnsafeCell::new(0));
+    let item_hdrs = [(); 1].map(|_| ItemHdr::default());
+    let storage = Storage::new(&header, &items, &item_hdrs);
+
+    let token = storage.put(|x| { *x = 1; true }).unwrap();
+
+    // a read guard can be held across a scope and re-derefed as many times as needed
+    {
+        let guard = storage.read(Token { id: token.id }).unwrap();
+        assert_eq!(*guard, 1);
+        assert!(storage.read(Token { id: token.id }).is_some());
+    }
+
+    // a write guard lets the caller mutate in place, then releases the slot as present on drop
+    {
+        let mut guard = storage.write(Token { id: token.id }).unwrap();
+        *guard = 2;
+        assert!(storage.write(Token { id: token.id }).is_none());
+    }
+    assert_eq!(*storage.read(Token { id: token.id }).unwrap(), 2);
+
+    // clearing the write guard releases the slot as empty
+    storage.write(Token { id: token.id }).unwrap().clear();
+    assert!(storage.read(Token { id: token.id }).is_none());
+}
+
+#[test]
+fn test_put_blocking_wakes_when_a_non_parked_slot_frees() {
+    // put_blocking must notice capacity freed up anywhere in the ring, not just on whichever
+    // slot it happened to check last before parking.
+    struct RingStorage {
+        header: StorageHdr,
+        items: [UnsafeCell<i32>; 2],
+        item_hdrs: [ItemHdr; 2],
+    }
+    unsafe impl Sync for RingStorage {}
+    fn view(ring: &RingStorage) -> Storage<'_, i32> {
+        Storage::new(&ring.header, &ring.items, &ring.item_hdrs)
+    }
+
+    let ring = RingStorage {
+        header: StorageHdr::new(2),
+        items: [UnsafeCell::new(0), UnsafeCell::new(0)],
+        item_hdrs: [ItemHdr::default(), ItemHdr::default()],
+    };
+    let storage = view(&ring);
+    let t0 = storage.put(|x| { *x = 1; true }).unwrap();
+    let t1 = storage.put(|x| { *x = 2; true }).unwrap();
+
+    // hold a read lock on both slots so neither is actually reclaimable yet, forcing
+    // put_blocking to park instead of reclaiming one on its first sweep
+    let guard0 = storage.read(Token { id: t0.id }).unwrap();
+    let guard1 = storage.read(Token { id: t1.id }).unwrap();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            view(&ring).put_blocking(|x| { *x = 3; true });
+            tx.send(()).unwrap();
+        });
+        // give the blocked putter a moment to sweep both full slots and park
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        // free slot 0, the slot the putter's sweep checked *first* rather than last
+        drop(guard0);
+        rx.recv_timeout(std::time::Duration::from_secs(2))
+            .expect("put_blocking never woke up after a different slot freed");
+    });
+    drop(guard1);
+}
+
 #[test]
 fn test_lock_api() {
     let lock = Lock::default();
@@ -218,3 +1077,127 @@ fn test_lock_api() {
         None
     );
 }
+
+// Exhaustive interleaving checks for the Lock state machine, run with
+// `RUSTFLAGS="--cfg loom" cargo test --release loom_ -- --test-threads=1`. Ordinary `cargo
+// test` never compiles this module, since loom's own synchronization primitives replace
+// std's for the duration of a model and must not be mixed with a real thread scheduler.
+#[cfg(loom)]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn loom_concurrent_create_only_one_wins() {
+        loom::model(|| {
+            let lock = Arc::new(Lock::default());
+            let successes = Arc::new(AtomicUsize::new(0));
+
+            let threads: Vec<_> = (0..2)
+                .map(|_| {
+                    let lock = lock.clone();
+                    let successes = successes.clone();
+                    thread::spawn(move || {
+                        if lock.create(|| Some(())).is_some() {
+                            successes.fetch_add(1, Ordering::Relaxed);
+                        }
+                    })
+                })
+                .collect();
+            for t in threads {
+                t.join().unwrap();
+            }
+            // exactly one of the two concurrent creates may ever see refcount == 0
+            assert_eq!(successes.load(Ordering::Relaxed), 1);
+        });
+    }
+
+    #[test]
+    fn loom_update_and_read_never_overlap() {
+        loom::model(|| {
+            let lock = Arc::new(Lock::default());
+            assert_eq!(lock.create(|| Some(0)), Some(0));
+
+            let reader = {
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    lock.read(|| {
+                        // update() only succeeds from refcount == 1, and read() holds it at
+                        // >= 2 for the duration of this closure, so it can never observe -1
+                        assert_ne!(lock.refcount.load(Ordering::Relaxed), -1);
+                    });
+                })
+            };
+            lock.update(|| {
+                assert_eq!(lock.refcount.load(Ordering::Relaxed), -1);
+                Some(1)
+            });
+            reader.join().unwrap();
+        });
+    }
+
+    // Bundles a single-slot ring's backing storage so it can be shared across loom threads via
+    // an `Arc`. `ItemHdr`/`UnsafeCell` are intentionally not `Sync` on their own, since ordinary
+    // callers are expected to synchronize access through `Lock`; this wrapper asserts that the
+    // same discipline holds here too.
+    struct RingStorage {
+        header: StorageHdr,
+        items: [UnsafeCell<i32>; 1],
+        item_hdrs: [ItemHdr; 1],
+    }
+    unsafe impl Sync for RingStorage {}
+
+    #[test]
+    fn loom_put_and_get_never_observe_a_torn_slot() {
+        loom::model(|| {
+            let ring = Arc::new(RingStorage {
+                header: StorageHdr::new(1),
+                items: [UnsafeCell::new(0)],
+                item_hdrs: [ItemHdr::default()],
+            });
+
+            let token = Storage::new(&ring.header, &ring.items, &ring.item_hdrs)
+                .put(|x| { *x = 1; true })
+                .unwrap();
+            let id = token.id;
+
+            let getter = {
+                let ring = ring.clone();
+                thread::spawn(move || {
+                    let storage = Storage::new(&ring.header, &ring.items, &ring.item_hdrs);
+                    // either the id hasn't been overwritten yet (Some) or the generation
+                    // check in get() rejected the stale token (None); never a torn read
+                    let r = storage.get(Token { id }, |x| *x);
+                    assert!(matches!(r, Some(1) | None));
+                })
+            };
+            Storage::new(&ring.header, &ring.items, &ring.item_hdrs)
+                .put(|x| { *x = 2; true });
+            getter.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn loom_subscribe_and_reserve_put_never_disagree_about_ownership() {
+        loom::model(|| {
+            let header = Arc::new(StorageHdr::new(1));
+
+            let subscriber = {
+                let header = header.clone();
+                thread::spawn(move || header.register_subscriber())
+            };
+            let (id, subscribers) = header.reserve_put();
+            let cursor = subscriber.join().unwrap();
+
+            // the subscriber must be armed for exactly the ids its cursor says it's owed,
+            // and owed exactly the ids it was armed for — never a mismatch that would leave
+            // a pending-read credit it can neither pay off via next() nor via Drop
+            if cursor <= id {
+                assert_eq!(subscribers, 1);
+            } else {
+                assert_eq!(subscribers, 0);
+            }
+        });
+    }
+}